@@ -1,97 +1,708 @@
 use anyhow::{Context, Result};
+use async_trait::async_trait;
 use dotenv::dotenv;
 use imap::Session;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+use mail_parser::MimeHeaders;
 use native_tls::TlsStream;
+use serde::{Deserialize, Serialize};
 use serenity::all::{CreateMessage, Http, UserId};
 use std::collections::HashSet;
 use std::env;
+use std::io::{Read, Write};
 use std::net::TcpStream;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 use tokio::time;
 use tracing::{error, info, warn};
 
-type ImapSession = Session<TlsStream<TcpStream>>;
+/// The underlying transport for an IMAP connection. A single enum lets implicit
+/// TLS, STARTTLS-upgraded, and plaintext connections share one session type.
+enum ImapStream {
+    Tls(TlsStream<TcpStream>),
+    Plain(TcpStream),
+}
 
-struct MailNotifier {
-    discord_http: Http,
+impl Read for ImapStream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            ImapStream::Tls(s) => s.read(buf),
+            ImapStream::Plain(s) => s.read(buf),
+        }
+    }
+}
+
+impl Write for ImapStream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            ImapStream::Tls(s) => s.write(buf),
+            ImapStream::Plain(s) => s.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            ImapStream::Tls(s) => s.flush(),
+            ImapStream::Plain(s) => s.flush(),
+        }
+    }
+}
+
+impl imap::extensions::idle::SetReadTimeout for ImapStream {
+    fn set_read_timeout(&mut self, timeout: Option<Duration>) -> imap::error::Result<()> {
+        match self {
+            ImapStream::Tls(s) => s.get_ref().set_read_timeout(timeout),
+            ImapStream::Plain(s) => s.set_read_timeout(timeout),
+        }
+        .map_err(imap::error::Error::Io)
+    }
+}
+
+type ImapSession = Session<ImapStream>;
+
+/// Top-level `accounts.toml` document: a list of inboxes to watch.
+#[derive(Debug, Deserialize)]
+struct Config {
+    #[serde(rename = "account")]
+    accounts: Vec<AccountConfig>,
+}
+
+/// A single IMAP inbox and the backends its notifications are routed to.
+#[derive(Debug, Clone, Deserialize)]
+struct AccountConfig {
+    name: String,
+    server: String,
+    port: u16,
+    username: String,
+    /// App password or login password. Used only when `oauth_token` is unset
+    /// or `XOAUTH2` authentication fails.
+    #[serde(default)]
+    password: String,
+    /// OAuth2 bearer token for `AUTH=XOAUTH2`, preferred over `password` when
+    /// present (e.g. Gmail/Outlook accounts with app passwords disabled).
+    #[serde(default)]
+    oauth_token: Option<String>,
+    /// How to secure the connection. Defaults to implicit TLS (port 993).
+    #[serde(default)]
+    security: ImapSecurity,
+    /// Maximum number of characters of body text included in notifications.
+    #[serde(default = "default_snippet_length")]
+    snippet_length: usize,
+    /// One or more destinations every new mail for this account is sent to.
+    notifiers: Vec<NotifierConfig>,
+}
+
+/// Transport security for the IMAP connection.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ImapSecurity {
+    /// Implicit TLS from the first byte (the standard IMAPS port 993).
+    #[default]
+    Tls,
+    /// Connect in plaintext then upgrade with the `STARTTLS` command.
+    Starttls,
+    /// No encryption (only sensible for localhost / trusted networks).
+    Plaintext,
+}
+
+fn default_snippet_length() -> usize {
+    280
+}
+
+/// SASL `XOAUTH2` authenticator: emits the `user=...\x01auth=Bearer ...` string
+/// the server expects for OAuth2 login.
+struct XOAuth2 {
+    user: String,
+    token: String,
+}
+
+impl imap::Authenticator for XOAuth2 {
+    type Response = String;
+
+    fn process(&self, _challenge: &[u8]) -> Self::Response {
+        format!("user={}\x01auth=Bearer {}\x01\x01", self.user, self.token)
+    }
+}
+
+/// A configured notification destination. Tagged by `type` in TOML, e.g.
+/// `type = "discord"` / `type = "email"` / `type = "webhook"`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum NotifierConfig {
+    Discord {
+        token: String,
+        user_id: u64,
+    },
+    Email {
+        smtp_host: String,
+        username: String,
+        password: String,
+        from: String,
+        to: String,
+    },
+    Webhook {
+        url: String,
+    },
+}
+
+/// A new message, handed to every configured [`Notifier`].
+#[derive(Debug, Clone, serde::Serialize)]
+struct MailEvent {
+    account: String,
+    from: String,
+    subject: String,
+    date: String,
+    uid: u32,
+    /// A short plain-text excerpt of the body, empty when none could be parsed.
+    snippet: String,
+    /// Attachment filenames, in the order they appear in the message.
+    attachments: Vec<String>,
+}
+
+impl MailEvent {
+    /// Render the event as the human-facing text used by chat backends.
+    fn to_message(&self) -> String {
+        let mut message = format!(
+            "📧 **New Email Received!** (`{account}`)\n\n\
+            **From:** {from}\n\
+            **Subject:** {subject}\n\
+            **Date:** {date}\n\
+            **UID:** {uid}",
+            account = self.account,
+            from = self.from,
+            subject = self.subject,
+            date = self.date,
+            uid = self.uid,
+        );
+
+        if !self.snippet.is_empty() {
+            message.push_str(&format!("\n\n{}", self.snippet));
+        }
+
+        if !self.attachments.is_empty() {
+            message.push_str(&format!(
+                "\n\n📎 {} attachment{}: {}",
+                self.attachments.len(),
+                if self.attachments.len() == 1 { "" } else { "s" },
+                self.attachments.join(", "),
+            ));
+        }
+
+        message
+    }
+}
+
+/// Per-account progress persisted across restarts.
+///
+/// `uidvalidity` pins the mailbox generation: if the server reports a different
+/// value the stored UIDs are meaningless and must be discarded. `last_uid` is
+/// the highest UID already processed: notified about, or present when the
+/// mailbox was seeded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AccountState {
+    uidvalidity: u32,
+    last_uid: u32,
+}
+
+/// A small on-disk JSON store, one file per account under a state directory.
+struct StateStore {
+    path: PathBuf,
+}
+
+impl StateStore {
+    fn for_account(dir: &Path, account: &str) -> Self {
+        // Keep filenames filesystem-safe regardless of the account name.
+        let safe: String = account
+            .chars()
+            .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect();
+        StateStore {
+            path: dir.join(format!("{safe}.json")),
+        }
+    }
+
+    fn load(&self) -> Result<Option<AccountState>> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(raw) => Ok(Some(
+                serde_json::from_str(&raw).context("Failed to parse state file")?,
+            )),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", self.path.display())),
+        }
+    }
+
+    fn save(&self, state: &AccountState) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create state dir {}", parent.display()))?;
+        }
+        let raw = serde_json::to_string(state).context("Failed to serialize state")?;
+        std::fs::write(&self.path, raw)
+            .with_context(|| format!("Failed to write {}", self.path.display()))?;
+        Ok(())
+    }
+}
+
+/// A destination that new-mail events are delivered to.
+#[async_trait]
+trait Notifier: Send + Sync {
+    async fn notify(&self, event: &MailEvent) -> Result<()>;
+}
+
+/// Direct-messages a Discord user.
+struct DiscordNotifier {
+    http: Http,
     user_id: UserId,
+}
+
+#[async_trait]
+impl Notifier for DiscordNotifier {
+    async fn notify(&self, event: &MailEvent) -> Result<()> {
+        let dm_channel = self
+            .user_id
+            .create_dm_channel(&self.http)
+            .await
+            .context("Failed to create DM channel")?;
+
+        let builder = CreateMessage::new().content(event.to_message());
+        dm_channel
+            .send_message(&self.http, builder)
+            .await
+            .context("Failed to send Discord message")?;
+
+        info!("Discord DM sent successfully");
+        Ok(())
+    }
+}
+
+/// Relays the event as an email over SMTP with implicit TLS.
+struct EmailNotifier {
+    smtp_host: String,
+    credentials: Credentials,
+    from: String,
+    to: String,
+}
+
+#[async_trait]
+impl Notifier for EmailNotifier {
+    async fn notify(&self, event: &MailEvent) -> Result<()> {
+        let message = Message::builder()
+            .from(self.from.parse().context("Invalid SMTP 'from' address")?)
+            .to(self.to.parse().context("Invalid SMTP 'to' address")?)
+            .subject(format!("[{}] {}", event.account, event.subject))
+            .body(event.to_message())
+            .context("Failed to build email message")?;
+
+        let host = self.smtp_host.clone();
+        let credentials = self.credentials.clone();
+
+        // lettre's SMTP transport is blocking, so run it off the async runtime.
+        tokio::task::spawn_blocking(move || {
+            let mailer = SmtpTransport::relay(&host)
+                .context("Failed to build SMTP transport")?
+                .credentials(credentials)
+                .build();
+            mailer.send(&message).context("Failed to send email")?;
+            Ok::<(), anyhow::Error>(())
+        })
+        .await
+        .context("SMTP task panicked")??;
+
+        info!("Notification email sent successfully");
+        Ok(())
+    }
+}
+
+/// POSTs the event as a JSON payload to an arbitrary webhook URL.
+struct WebhookNotifier {
+    url: String,
+    client: reqwest::Client,
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    async fn notify(&self, event: &MailEvent) -> Result<()> {
+        self.client
+            .post(&self.url)
+            .json(event)
+            .send()
+            .await
+            .context("Failed to POST to webhook")?
+            .error_for_status()
+            .context("Webhook returned an error status")?;
+
+        info!("Webhook notification sent successfully");
+        Ok(())
+    }
+}
+
+impl NotifierConfig {
+    /// Instantiate the concrete backend described by this config entry.
+    fn build(&self) -> Result<Box<dyn Notifier>> {
+        Ok(match self {
+            NotifierConfig::Discord { token, user_id } => Box::new(DiscordNotifier {
+                http: Http::new(token),
+                user_id: UserId::new(*user_id),
+            }),
+            NotifierConfig::Email {
+                smtp_host,
+                username,
+                password,
+                from,
+                to,
+            } => Box::new(EmailNotifier {
+                smtp_host: smtp_host.clone(),
+                credentials: Credentials::new(username.clone(), password.clone()),
+                from: from.clone(),
+                to: to.clone(),
+            }),
+            NotifierConfig::Webhook { url } => Box::new(WebhookNotifier {
+                url: url.clone(),
+                client: reqwest::Client::new(),
+            }),
+        })
+    }
+}
+
+impl Config {
+    fn load(path: &Path) -> Result<Self> {
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        let config: Config =
+            toml::from_str(&raw).context("Failed to parse accounts config as TOML")?;
+        if config.accounts.is_empty() {
+            anyhow::bail!("No accounts configured in {}", path.display());
+        }
+        Ok(config)
+    }
+}
+
+/// Perform a plaintext IMAP `STARTTLS` handshake on `socket` and return the
+/// TLS-upgraded stream, ready for a fresh `Client`. Reads the server greeting
+/// and the tagged `STARTTLS` response directly off the socket so it stays
+/// independent of the IMAP client's connection state.
+fn starttls_upgrade(socket: TcpStream, domain: &str) -> Result<TlsStream<TcpStream>> {
+    use std::io::{BufRead, BufReader};
+
+    let mut reader = BufReader::new(socket.try_clone().context("Failed to clone socket")?);
+    let mut line = String::new();
+    reader
+        .read_line(&mut line)
+        .context("Failed to read server greeting")?;
+
+    let mut socket = socket;
+    socket
+        .write_all(b"a001 STARTTLS\r\n")
+        .context("Failed to send STARTTLS")?;
+
+    line.clear();
+    reader
+        .read_line(&mut line)
+        .context("Failed to read STARTTLS response")?;
+    if !line.to_ascii_uppercase().contains("A001 OK") {
+        anyhow::bail!("Server rejected STARTTLS: {}", line.trim());
+    }
+
+    let tls = native_tls::TlsConnector::builder()
+        .build()
+        .context("Failed to create TLS connector")?;
+    tls.connect(domain, socket)
+        .context("TLS handshake failed after STARTTLS")
+}
+
+/// Extract a plain-text snippet from a parsed message, truncated to `max_len`.
+///
+/// Prefers the `text/plain` part and falls back to crudely stripping tags from
+/// `text/html`. Whitespace is collapsed so the snippet stays on a few lines.
+fn body_snippet(message: &mail_parser::Message, max_len: usize) -> String {
+    let text = message
+        .body_text(0)
+        .map(|t| t.into_owned())
+        .or_else(|| message.body_html(0).map(|html| strip_html(&html)))
+        .unwrap_or_default();
+
+    let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    truncate_chars(&collapsed, max_len)
+}
+
+/// Collect the filenames of every attachment part, falling back to a
+/// placeholder when a part has no name.
+fn attachment_names(message: &mail_parser::Message) -> Vec<String> {
+    message
+        .attachments()
+        .map(|part| {
+            part.attachment_name()
+                .unwrap_or("unnamed")
+                .to_string()
+        })
+        .collect()
+}
+
+/// Remove HTML tags, leaving the text content.
+fn strip_html(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for ch in html.chars() {
+        match ch {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => out.push(ch),
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Truncate to at most `max_len` characters (not bytes), appending an ellipsis
+/// when the text was cut.
+fn truncate_chars(text: &str, max_len: usize) -> String {
+    if text.chars().count() <= max_len {
+        return text.to_string();
+    }
+    let mut out: String = text.chars().take(max_len).collect();
+    out.push('…');
+    out
+}
+
+struct MailNotifier {
+    account: AccountConfig,
+    notifiers: Vec<Box<dyn Notifier>>,
     imap_session: ImapSession,
     seen_uids: HashSet<u32>,
+    idle_capable: bool,
+    store: StateStore,
+    uidvalidity: u32,
+    last_uid: u32,
 }
 
+/// How long to hold a single IDLE before re-issuing it. RFC 2177 requires
+/// re-entering IDLE at least every ~29 minutes, so we stay comfortably under.
+const IDLE_KEEPALIVE: Duration = Duration::from_secs(25 * 60);
+
 impl MailNotifier {
-    async fn new() -> Result<Self> {
-        let discord_token =
-            env::var("DISCORD_TOKEN").context("DISCORD_TOKEN environment variable not set")?;
-        let user_id: u64 = env::var("DISCORD_USER_ID")
-            .context("DISCORD_USER_ID environment variable not set")?
-            .parse()
-            .context("Invalid DISCORD_USER_ID format")?;
-        let gmail_email =
-            env::var("GMAIL_EMAIL").context("GMAIL_EMAIL environment variable not set")?;
-        let gmail_password = env::var("GMAIL_APP_PASSWORD")
-            .context("GMAIL_APP_PASSWORD environment variable not set")?;
-
-        let discord_http = Http::new(&discord_token);
-        let user_id = UserId::new(user_id);
-
-        let domain = "imap.gmail.com";
-        let port = 993;
-        let socket =
-            TcpStream::connect((domain, port)).context("Failed to connect to Gmail IMAP server")?;
-
-        let tls = native_tls::TlsConnector::builder()
-            .build()
-            .context("Failed to create TLS connector")?;
-        let tls_stream = tls
-            .connect(domain, socket)
-            .context("Failed to establish TLS connection")?;
-
-        let client = imap::Client::new(tls_stream);
-        let mut imap_session = client
-            .login(&gmail_email, &gmail_password)
-            .map_err(|e| anyhow::anyhow!("IMAP login failed: {:?}", e.0))?;
-
-        imap_session
-            .select("INBOX")
-            .context("Failed to select INBOX")?;
+    /// Open a TCP connection per the account's security setting and
+    /// authenticate. Shared by `new` and `reconnect` so the transport setup
+    /// lives in exactly one place.
+    ///
+    /// `XOAUTH2` is preferred whenever an `oauth_token` is configured,
+    /// falling back to a password `LOGIN` if the server rejects it.
+    fn connect_session(account: &AccountConfig) -> Result<ImapSession> {
+        let socket = TcpStream::connect((account.server.as_str(), account.port))
+            .with_context(|| format!("Failed to connect to IMAP server for {}", account.name))?;
+
+        // For STARTTLS the greeting is consumed during the upgrade handshake;
+        // for the other modes the fresh `Client` still needs to read it
+        // before we can authenticate.
+        let (stream, greeting_read) = match account.security {
+            ImapSecurity::Plaintext => (ImapStream::Plain(socket), false),
+            ImapSecurity::Tls => {
+                let tls = native_tls::TlsConnector::builder()
+                    .build()
+                    .context("Failed to create TLS connector")?;
+                let tls_stream = tls
+                    .connect(&account.server, socket)
+                    .context("Failed to establish TLS connection")?;
+                (ImapStream::Tls(tls_stream), false)
+            }
+            ImapSecurity::Starttls => {
+                let tls_stream = starttls_upgrade(socket, &account.server)
+                    .context("Failed to upgrade connection with STARTTLS")?;
+                (ImapStream::Tls(tls_stream), true)
+            }
+        };
+
+        let mut client = imap::Client::new(stream);
+        if !greeting_read {
+            client.read_greeting().context("Failed to read greeting")?;
+        }
 
-        let mut seen_uids = HashSet::new();
+        // `imap::Client` doesn't expose a pre-auth `CAPABILITY` in its public
+        // API, so we can't decide XOAUTH2-vs-LOGIN up front. Instead prefer
+        // XOAUTH2 whenever a token is configured and fall back to password
+        // `LOGIN` if the server rejects it (e.g. it doesn't support SASL
+        // XOAUTH2 at all).
+        let session = match &account.oauth_token {
+            Some(token) => {
+                let auth = XOAuth2 {
+                    user: account.username.clone(),
+                    token: token.clone(),
+                };
+                match client.authenticate("XOAUTH2", &auth) {
+                    Ok(session) => session,
+                    Err((e, client)) => {
+                        warn!(
+                            "[{}] XOAUTH2 authentication failed ({:?}), falling back to password login",
+                            account.name, e
+                        );
+                        client
+                            .login(&account.username, &account.password)
+                            .map_err(|e| anyhow::anyhow!("IMAP login failed: {:?}", e.0))?
+                    }
+                }
+            }
+            None => client
+                .login(&account.username, &account.password)
+                .map_err(|e| anyhow::anyhow!("IMAP login failed: {:?}", e.0))?,
+        };
 
-        let messages = imap_session
-            .search("ALL")
-            .context("Failed to search existing messages")?;
-        for uid in messages {
-            seen_uids.insert(uid);
+        Ok(session)
+    }
+
+    async fn new(account: AccountConfig, state_dir: &Path) -> Result<Self> {
+        if account.notifiers.is_empty() {
+            anyhow::bail!("Account '{}' has no notifiers configured", account.name);
         }
+        let notifiers = account
+            .notifiers
+            .iter()
+            .map(NotifierConfig::build)
+            .collect::<Result<Vec<_>>>()
+            .context("Failed to build notifiers")?;
 
-        info!("Initialized with {} existing messages", seen_uids.len());
+        let mut imap_session = Self::connect_session(&account)?;
 
-        Ok(Self {
-            discord_http,
-            user_id,
+        let mailbox = imap_session
+            .select("INBOX")
+            .context("Failed to select INBOX")?;
+        let uidvalidity = mailbox
+            .uid_validity
+            .context("Server did not report UIDVALIDITY")?;
+
+        let idle_capable = imap_session
+            .capabilities()
+            .map(|caps| caps.has_str("IDLE"))
+            .unwrap_or(false);
+        if idle_capable {
+            info!("[{}] Server advertises IDLE; using push notifications", account.name);
+        } else {
+            warn!("[{}] Server does not advertise IDLE; falling back to 30s polling", account.name);
+        }
+
+        let store = StateStore::for_account(state_dir, &account.name);
+        let stored = store.load()?;
+
+        // Decide whether to resume (same mailbox generation, so `catch_up`
+        // later re-notifies missed mail) or silently reseed (new UIDVALIDITY /
+        // first run). Yields the set of UIDs to treat as already processed
+        // plus the highest such UID.
+        let (seen_uids, last_uid) = match stored {
+            Some(state) if state.uidvalidity == uidvalidity => {
+                info!("[{}] Resuming from UID {}", account.name, state.last_uid);
+                (HashSet::new(), state.last_uid)
+            }
+            other => {
+                if other.is_some() {
+                    warn!("[{}] UIDVALIDITY changed; discarding stored state", account.name);
+                }
+                let existing = imap_session
+                    .uid_search("ALL")
+                    .context("Failed to search existing messages")?;
+                let last_uid = existing.iter().copied().max().unwrap_or(0);
+                info!(
+                    "[{}] Seeded {} existing messages (up to UID {})",
+                    account.name,
+                    existing.len(),
+                    last_uid
+                );
+                (existing, last_uid)
+            }
+        };
+
+        let notifier = Self {
+            account,
+            notifiers,
             imap_session,
             seen_uids,
+            idle_capable,
+            store,
+            uidvalidity,
+            last_uid,
+        };
+        notifier.persist_state()?;
+
+        Ok(notifier)
+    }
+
+    /// Write the current `uidvalidity`/`last_uid` to disk so progress survives
+    /// crashes and restarts.
+    fn persist_state(&self) -> Result<()> {
+        self.store.save(&AccountState {
+            uidvalidity: self.uidvalidity,
+            last_uid: self.last_uid,
+        })
+    }
+
+    /// Notify about any messages that arrived while the tool was offline, i.e.
+    /// UIDs greater than the last one we persisted. No-op on a fresh reseed.
+    async fn catch_up(&mut self) -> Result<()> {
+        if self.last_uid == 0 {
+            return Ok(());
+        }
+        let missed = self
+            .imap_session
+            .uid_search(format!("UID {}:*", self.last_uid + 1))
+            .context("Failed to search for missed messages")?;
+        for uid in missed {
+            // `UID n:*` always returns at least the highest UID even when none
+            // are strictly greater, so guard against reprocessing it.
+            if uid > self.last_uid && self.seen_uids.insert(uid) {
+                if let Err(e) = self.process_new_email(uid).await {
+                    error!("[{}] Failed to process missed email {}: {}", self.account.name, uid, e);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Block until the server reports mailbox activity.
+    ///
+    /// When the server supports IDLE we enter it and wait for an untagged
+    /// `EXISTS`/`RECENT`, re-issuing IDLE at [`IDLE_KEEPALIVE`] so the
+    /// connection never exceeds the RFC 2177 window. A keepalive timeout is not
+    /// an error: IDLE is re-issued and the wait continues. Without IDLE we
+    /// fall back to a single `NOOP` poll.
+    ///
+    /// This blocks the calling OS thread for as long as [`IDLE_KEEPALIVE`], so
+    /// it runs via `block_in_place`: with one account monitored per tokio
+    /// task, a plain blocking call here would tie up a worker thread for the
+    /// whole IDLE wait, starving other accounts' tasks once there are more
+    /// accounts than runtime worker threads.
+    fn wait_for_activity(&mut self) -> Result<()> {
+        tokio::task::block_in_place(|| {
+            if !self.idle_capable {
+                self.imap_session
+                    .noop()
+                    .context("Failed to send NOOP command")?;
+                return Ok(());
+            }
+
+            let mut idle = self
+                .imap_session
+                .idle()
+                .context("Failed to enter IDLE")?;
+            idle.set_keepalive(IDLE_KEEPALIVE);
+            idle.wait_keepalive().context("IDLE wait failed")?;
+            Ok(())
         })
     }
 
     async fn check_new_emails(&mut self) -> Result<()> {
-        self.imap_session
-            .noop()
-            .context("Failed to send NOOP command")?;
+        self.wait_for_activity()?;
 
         let messages = self
             .imap_session
-            .search("UNSEEN")
+            .uid_search("UNSEEN")
             .context("Failed to search for unseen messages")?;
 
         for uid in messages {
-            if !self.seen_uids.contains(&uid) {
-                self.seen_uids.insert(uid);
-
+            // Mirror `catch_up`'s guard: `UNSEEN` reflects the server's IMAP
+            // "seen" flag, not what we've already notified about, so a UID we
+            // processed last run but the user hasn't opened yet would
+            // otherwise look new again on every restart.
+            if uid > self.last_uid && self.seen_uids.insert(uid) {
                 if let Err(e) = self.process_new_email(uid).await {
-                    error!("Failed to process email {}: {}", uid, e);
+                    error!("[{}] Failed to process email {}: {}", self.account.name, uid, e);
                 }
             }
         }
@@ -102,7 +713,7 @@ impl MailNotifier {
     async fn process_new_email(&mut self, uid: u32) -> Result<()> {
         let messages = self
             .imap_session
-            .fetch(format!("{uid}"), "(ENVELOPE BODY[HEADER.FIELDS (DATE)])")
+            .uid_fetch(format!("{uid}"), "(ENVELOPE BODY.PEEK[])")
             .context("Failed to fetch email")?;
 
         if let Some(message) = messages.iter().next() {
@@ -132,7 +743,7 @@ impl MailNotifier {
                 })
                 .unwrap_or_else(|| "Unknown Sender".to_string());
 
-            let subject = envelope
+            let envelope_subject = envelope
                 .subject
                 .as_ref()
                 .map(|s| std::str::from_utf8(s).unwrap_or("No Subject"))
@@ -142,51 +753,78 @@ impl MailNotifier {
                 .date
                 .as_ref()
                 .map(|d| std::str::from_utf8(d).unwrap_or("Unknown Date"))
-                .unwrap_or("Unknown Date");
+                .unwrap_or("Unknown Date")
+                .to_string();
 
-            let notification = format!(
-                "📧 **New Email Received!**\n\n\
-                **From:** {from}\n\
-                **Subject:** {subject}\n\
-                **Date:** {date}\n\
-                **UID:** {uid}"
-            );
+            // Parse the full message for a body snippet, attachment list, and a
+            // properly charset-decoded subject (the envelope subject is still
+            // MIME word-encoded).
+            let parsed = message
+                .body()
+                .and_then(|raw| mail_parser::MessageParser::default().parse(raw));
 
-            info!("New email from: {} - Subject: {}", from, subject);
-
-            self.send_discord_dm(&notification)
-                .await
-                .context("Failed to send Discord DM")?;
-        }
-
-        Ok(())
-    }
+            let subject = parsed
+                .as_ref()
+                .and_then(|m| m.subject())
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| envelope_subject.to_string());
 
-    async fn send_discord_dm(&self, message: &str) -> Result<()> {
-        let dm_channel = self
-            .user_id
-            .create_dm_channel(&self.discord_http)
-            .await
-            .context("Failed to create DM channel")?;
+            let snippet = parsed
+                .as_ref()
+                .map(|m| body_snippet(m, self.account.snippet_length))
+                .unwrap_or_default();
 
-        let builder = CreateMessage::new().content(message);
+            let attachments = parsed
+                .as_ref()
+                .map(attachment_names)
+                .unwrap_or_default();
+
+            let event = MailEvent {
+                account: self.account.name.clone(),
+                from: from.clone(),
+                subject: subject.clone(),
+                date,
+                uid,
+                snippet,
+                attachments,
+            };
+
+            info!("[{}] New email from: {} - Subject: {}", self.account.name, from, subject);
+
+            for notifier in &self.notifiers {
+                if let Err(e) = notifier.notify(&event).await {
+                    error!("[{}] Notifier failed: {:#}", self.account.name, e);
+                }
+            }
 
-        dm_channel
-            .send_message(&self.discord_http, builder)
-            .await
-            .context("Failed to send Discord message")?;
+            // Record progress so a crash mid-run doesn't replay this message.
+            if uid > self.last_uid {
+                self.last_uid = uid;
+                if let Err(e) = self.persist_state() {
+                    error!("[{}] Failed to persist state: {}", self.account.name, e);
+                }
+            }
+        }
 
-        info!("Discord DM sent successfully");
         Ok(())
     }
 
     async fn run(&mut self) -> Result<()> {
-        info!("Mail notifier started. Checking for new emails every 30 seconds...");
+        if self.idle_capable {
+            info!("[{}] Mail notifier started. Waiting for IDLE push notifications...", self.account.name);
+        } else {
+            info!("[{}] Mail notifier started. Checking for new emails every 30 seconds...", self.account.name);
+        }
+
+        // Deliver anything that arrived while we were offline before waiting.
+        self.catch_up().await?;
 
         let mut interval = time::interval(Duration::from_secs(30));
 
         loop {
-            interval.tick().await;
+            if !self.idle_capable {
+                interval.tick().await;
+            }
 
             if let Err(e) = self.check_new_emails().await {
                 error!("Error checking emails: {}", e);
@@ -197,32 +835,68 @@ impl MailNotifier {
                         error!("Failed to reconnect: {}", reconnect_err);
                         time::sleep(Duration::from_secs(60)).await;
                     }
+                } else {
+                    // Not a disconnect: still back off before retrying so a
+                    // persistent protocol error (e.g. a BAD/NO re-entering
+                    // IDLE) doesn't busy-loop against the server.
+                    time::sleep(Duration::from_secs(30)).await;
                 }
             }
         }
     }
 
     async fn reconnect(&mut self) -> Result<()> {
-        let gmail_email = env::var("GMAIL_EMAIL")?;
-        let gmail_password = env::var("GMAIL_APP_PASSWORD")?;
-
-        let domain = "imap.gmail.com";
-        let port = 993;
-        let socket = TcpStream::connect((domain, port))?;
-
-        let tls = native_tls::TlsConnector::builder().build()?;
-        let tls_stream = tls.connect(domain, socket)?;
+        self.imap_session = Self::connect_session(&self.account)
+            .context("IMAP reconnection failed")?;
+
+        let mailbox = self.imap_session.select("INBOX")?;
+        let uidvalidity = mailbox
+            .uid_validity
+            .context("Server did not report UIDVALIDITY on reconnect")?;
+        if uidvalidity != self.uidvalidity {
+            warn!("[{}] UIDVALIDITY changed on reconnect; reseeding", self.account.name);
+            self.uidvalidity = uidvalidity;
+            self.seen_uids.clear();
+            self.last_uid = self
+                .imap_session
+                .uid_search("ALL")?
+                .iter()
+                .copied()
+                .max()
+                .unwrap_or(0);
+            self.persist_state()?;
+        }
 
-        let client = imap::Client::new(tls_stream);
-        self.imap_session = client
-            .login(&gmail_email, &gmail_password)
-            .map_err(|e| anyhow::anyhow!("IMAP reconnection failed: {:?}", e.0))?;
+        self.idle_capable = self
+            .imap_session
+            .capabilities()
+            .map(|caps| caps.has_str("IDLE"))
+            .unwrap_or(false);
 
-        self.imap_session.select("INBOX")?;
+        info!("[{}] Successfully reconnected to IMAP server", self.account.name);
 
-        info!("Successfully reconnected to IMAP server");
+        // Pick up anything that arrived during the outage.
+        self.catch_up().await?;
         Ok(())
     }
+
+    /// Run a single account's monitor forever: whenever `new` fails or `run`
+    /// exits, wait 60 seconds and start over, so a transient outage doesn't
+    /// kill the task.
+    async fn monitor(account: AccountConfig, state_dir: PathBuf) {
+        loop {
+            match MailNotifier::new(account.clone(), &state_dir).await {
+                Ok(mut notifier) => {
+                    if let Err(e) = notifier.run().await {
+                        error!("[{}] Monitor exited: {}", account.name, e);
+                    }
+                }
+                Err(e) => error!("[{}] Failed to initialize: {}", account.name, e),
+            }
+            warn!("[{}] Restarting monitor in 60 seconds", account.name);
+            time::sleep(Duration::from_secs(60)).await;
+        }
+    }
 }
 
 #[tokio::main]
@@ -240,35 +914,47 @@ async fn main() -> Result<()> {
     
     tracing_subscriber::fmt::init();
 
-    info!("Starting Gmail to Discord notifier...");
-
-    let required_vars = [
-        "DISCORD_TOKEN",
-        "DISCORD_USER_ID",
-        "GMAIL_EMAIL",
-        "GMAIL_APP_PASSWORD",
-    ];
-    for var in &required_vars {
-        if env::var(var).is_err() {
-            error!("Missing required environment variable: {}", var);
-            eprintln!("\nRequired environment variables:");
-            eprintln!("DISCORD_TOKEN=your_discord_bot_token");
-            eprintln!("DISCORD_USER_ID=your_discord_user_id");
-            eprintln!("GMAIL_EMAIL=your_gmail_address");
-            eprintln!("GMAIL_APP_PASSWORD=your_gmail_app_password");
-            eprintln!("\nNote: Use Gmail App Password, not your regular password!");
+    info!("Starting mail to Discord notifier...");
+
+    let config_path = env::var("ACCOUNTS_CONFIG")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("accounts.toml"));
+
+    let config = match Config::load(&config_path) {
+        Ok(config) => config,
+        Err(e) => {
+            error!("Failed to load config: {}", e);
+            eprintln!("\nExpected a TOML config at {} describing one or more accounts:", config_path.display());
+            eprintln!("\n[[account]]");
+            eprintln!("name = \"personal\"");
+            eprintln!("server = \"imap.gmail.com\"");
+            eprintln!("port = 993");
+            eprintln!("username = \"you@gmail.com\"");
+            eprintln!("password = \"your_app_password\"");
+            eprintln!();
+            eprintln!("[[account.notifiers]]");
+            eprintln!("type = \"discord\"");
+            eprintln!("token = \"your_discord_bot_token\"");
+            eprintln!("user_id = 123456789012345678");
+            eprintln!("\nSet ACCOUNTS_CONFIG to use a different path.");
             std::process::exit(1);
         }
-    }
+    };
 
-    let mut notifier = MailNotifier::new()
-        .await
-        .context("Failed to initialize mail notifier")?;
+    let state_dir = env::var("STATE_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("state"));
 
-    notifier
-        .run()
-        .await
-        .context("Mail notifier encountered an error")?;
+    info!("Watching {} account(s)", config.accounts.len());
+
+    let mut handles = Vec::with_capacity(config.accounts.len());
+    for account in config.accounts {
+        handles.push(tokio::spawn(MailNotifier::monitor(account, state_dir.clone())));
+    }
+
+    for handle in handles {
+        let _ = handle.await;
+    }
 
     Ok(())
 }